@@ -1,65 +1,260 @@
-use tracing::{debug, info, warn};
+use std::path::PathBuf;
+use std::sync::Mutex;
 
-/// Process information
+use sysinfo::{Pid, ProcessStatus, System};
+use tracing::{debug, info};
+
+/// Process information derived from the [`sysinfo`] backend.
 #[derive(Debug, Clone)]
 pub struct ProcessInfo {
     pub pid: u32,
     pub ppid: u32,
     pub name: String,
+    /// Full command line, as reported by the platform.
+    pub cmd: Vec<String>,
+    /// Working directory, when the platform exposes it.
+    pub cwd: Option<PathBuf>,
+    /// Human-readable run status (running, sleeping, zombie, ...).
+    pub status: String,
+    /// Resident memory in bytes.
+    pub memory: u64,
+    /// Process start time in seconds since the Unix epoch.
+    pub start_time: u64,
 }
 
-/// Handles process tree traversal and process information extraction
-pub struct ProcessTracker;
+/// The command currently running in the foreground of a session's terminal.
+#[derive(Debug, Clone)]
+pub struct ForegroundProcess {
+    pub pid: u32,
+    pub name: String,
+    pub cmd: Vec<String>,
+}
+
+impl From<ProcessInfo> for ForegroundProcess {
+    fn from(info: ProcessInfo) -> Self {
+        Self {
+            pid: info.pid,
+            name: info.name,
+            cmd: info.cmd,
+        }
+    }
+}
+
+/// Handles process tree traversal and process information extraction.
+///
+/// Backed by a cached [`sysinfo::System`] so that process lookups work
+/// uniformly across platforms without the layout-sensitive `kinfo_proc`
+/// structs, `ps` shell-outs, `/proc` parsing and per-call Toolhelp snapshots
+/// the previous implementation relied on.
+pub struct ProcessTracker {
+    system: Mutex<System>,
+}
 
 impl ProcessTracker {
-    /// Get the parent process ID of a given process
-    pub fn get_parent_process_id(pid: u32) -> Option<u32> {
-        #[cfg(target_os = "macos")]
-        {
-            Self::get_parent_pid_macos(pid)
+    /// Create a tracker with a freshly populated process table.
+    pub fn new() -> Self {
+        let mut system = System::new();
+        system.refresh_processes();
+        Self {
+            system: Mutex::new(system),
         }
-        #[cfg(target_os = "windows")]
-        {
-            Self::get_parent_pid_windows(pid)
+    }
+
+    /// Refresh the cached process table. Call this before a traversal when the
+    /// caller needs an up-to-date view of currently running processes.
+    pub fn refresh(&self) {
+        self.system.lock().unwrap().refresh_processes();
+    }
+
+    /// Get the parent process ID of a given process.
+    pub fn get_parent_process_id(&self, pid: u32) -> Option<u32> {
+        // On Windows a direct per-PID query is an O(1) hop; only fall back to
+        // the cached process table when that lookup yields no parent.
+        #[cfg(windows)]
+        if let Some(ppid) = Self::get_parent_pid_windows(pid) {
+            return Some(ppid);
         }
-        #[cfg(target_os = "linux")]
-        {
-            Self::get_parent_pid_linux(pid)
+
+        let system = self.system.lock().unwrap();
+        let process = system.process(Pid::from_u32(pid))?;
+        process.parent().map(|p| p.as_u32())
+    }
+
+    /// Get process info including name, parent PID and the richer fields
+    /// exposed by the [`sysinfo`] backend.
+    pub fn get_process_info(&self, pid: u32) -> Option<ProcessInfo> {
+        let system = self.system.lock().unwrap();
+        let process = system.process(Pid::from_u32(pid))?;
+        Some(Self::process_info(pid, process))
+    }
+
+    /// Build a [`ProcessInfo`] from a [`sysinfo::Process`].
+    fn process_info(pid: u32, process: &sysinfo::Process) -> ProcessInfo {
+        // On Windows prefer the direct `QueryFullProcessImageNameW` lookup,
+        // falling back to the `sysinfo` name for processes we cannot open.
+        #[cfg(windows)]
+        let name = Self::process_name_windows(pid).unwrap_or_else(|| process.name().to_string());
+        #[cfg(not(windows))]
+        let name = process.name().to_string();
+        ProcessInfo {
+            pid,
+            ppid: process.parent().map(|p| p.as_u32()).unwrap_or(0),
+            name,
+            cmd: process.cmd().to_vec(),
+            cwd: process.cwd().map(|p| p.to_path_buf()),
+            status: Self::status_label(process.status()),
+            memory: process.memory(),
+            start_time: process.start_time(),
         }
     }
 
-    /// Get process info including name and parent PID
-    pub fn get_process_info(pid: u32) -> Option<ProcessInfo> {
-        #[cfg(target_os = "macos")]
-        {
-            Self::get_process_info_macos(pid)
+    /// Build the full descendant tree under `root_pid`.
+    ///
+    /// Inverts the parent links in the cached process table into a
+    /// `ppid -> children` map and walks it breadth-first from `root_pid`,
+    /// returning every descendant PID. `MAX_DEPTH` caps the walk so a cycle in
+    /// the (normally acyclic) parent links can never spin forever.
+    pub fn descendant_tree(&self, root_pid: u32) -> Vec<u32> {
+        let system = self.system.lock().unwrap();
+
+        // Invert parent links: ppid -> children.
+        let mut children: std::collections::HashMap<u32, Vec<u32>> = std::collections::HashMap::new();
+        for (pid, process) in system.processes() {
+            if let Some(parent) = process.parent() {
+                children.entry(parent.as_u32()).or_default().push(pid.as_u32());
+            }
         }
-        #[cfg(target_os = "windows")]
-        {
-            Self::get_process_info_windows(pid)
+
+        const MAX_DEPTH: usize = 64;
+        let mut descendants = Vec::new();
+        let mut seen = std::collections::HashSet::new();
+        let mut frontier = vec![root_pid];
+        let mut depth = 0;
+
+        while !frontier.is_empty() && depth < MAX_DEPTH {
+            let mut next = Vec::new();
+            for pid in frontier {
+                if let Some(kids) = children.get(&pid) {
+                    for &child in kids {
+                        if seen.insert(child) {
+                            descendants.push(child);
+                            next.push(child);
+                        }
+                    }
+                }
+            }
+            frontier = next;
+            depth += 1;
         }
-        #[cfg(target_os = "linux")]
-        {
-            Self::get_process_info_linux(pid)
+
+        descendants
+    }
+
+    /// Identify the command currently in the foreground of a session's
+    /// terminal.
+    ///
+    /// On Unix the foreground process group is read from the PTY master with
+    /// `tcgetpgrp`; the descendant whose PID equals that group id is the
+    /// foreground command. If that leader has exited between enumeration and
+    /// lookup we fall back to the deepest descendant of the shell. On Windows
+    /// there is no process group, so we approximate with the most recently
+    /// started non-shell descendant.
+    #[cfg(unix)]
+    pub fn foreground_process(&self, pty_master_fd: std::os::unix::io::RawFd, root_pid: u32) -> Option<ForegroundProcess> {
+        let pgrp = unsafe { libc::tcgetpgrp(pty_master_fd) };
+
+        let descendants = self.descendant_tree(root_pid);
+        if pgrp > 0 {
+            let pgrp = pgrp as u32;
+            if descendants.contains(&pgrp) || pgrp == root_pid {
+                if let Some(info) = self.get_process_info(pgrp) {
+                    return Some(ForegroundProcess::from(info));
+                }
+            }
+        }
+
+        // Leader gone (or no tty): fall back to the deepest descendant.
+        self.deepest_descendant(root_pid, &descendants)
+    }
+
+    /// Windows foreground approximation: the most recently started descendant
+    /// that is not itself a shell.
+    #[cfg(windows)]
+    pub fn foreground_process(&self, root_pid: u32) -> Option<ForegroundProcess> {
+        let descendants = self.descendant_tree(root_pid);
+        descendants
+            .iter()
+            .filter_map(|&pid| self.get_process_info(pid))
+            .filter(|info| !Self::is_shell(&info.name))
+            .max_by_key(|info| info.start_time)
+            .map(ForegroundProcess::from)
+    }
+
+    /// Deepest descendant of `root_pid`, used as the Unix fallback when the
+    /// foreground group leader has already exited.
+    #[cfg(unix)]
+    fn deepest_descendant(&self, root_pid: u32, descendants: &[u32]) -> Option<ForegroundProcess> {
+        let mut best: Option<(usize, ProcessInfo)> = None;
+        for &pid in descendants {
+            let mut depth = 0;
+            let mut current = pid;
+            while current != root_pid && depth < 64 {
+                match self.get_parent_process_id(current) {
+                    Some(parent) => {
+                        current = parent;
+                        depth += 1;
+                    }
+                    None => break,
+                }
+            }
+            if current != root_pid {
+                continue;
+            }
+            if best.as_ref().map(|(d, _)| depth > *d).unwrap_or(true) {
+                if let Some(info) = self.get_process_info(pid) {
+                    best = Some((depth, info));
+                }
+            }
+        }
+        best.map(|(_, info)| ForegroundProcess::from(info))
+    }
+
+    #[cfg(windows)]
+    fn is_shell(name: &str) -> bool {
+        matches!(
+            name,
+            "sh" | "bash" | "zsh" | "fish" | "dash" | "tcsh" | "csh"
+                | "cmd.exe" | "powershell.exe" | "pwsh.exe"
+        )
+    }
+
+    fn status_label(status: ProcessStatus) -> String {
+        match status {
+            ProcessStatus::Run => "running".to_string(),
+            ProcessStatus::Sleep => "sleeping".to_string(),
+            ProcessStatus::Idle => "idle".to_string(),
+            ProcessStatus::Zombie => "zombie".to_string(),
+            ProcessStatus::Stop => "stopped".to_string(),
+            other => other.to_string(),
         }
     }
 
-    /// Log the process tree for debugging
-    pub fn log_process_tree(pid: u32) {
+    /// Log the process tree for debugging.
+    pub fn log_process_tree(&self, pid: u32) {
         debug!("Process tree for PID {}:", pid);
-        
+
         let mut current_pid = pid;
         let mut depth = 0;
-        
+
         while depth < 20 {
-            if let Some(info) = Self::get_process_info(current_pid) {
+            if let Some(info) = self.get_process_info(current_pid) {
                 let indent = "  ".repeat(depth);
                 debug!("{}PID {}: {} (parent: {})", indent, current_pid, info.name, info.ppid);
-                
+
                 if info.ppid == 0 || info.ppid == 1 {
                     break;
                 }
-                
+
                 current_pid = info.ppid;
                 depth += 1;
             } else {
@@ -68,302 +263,158 @@ impl ProcessTracker {
         }
     }
 
-    /// Find the terminal process in the ancestry of a given PID
-    pub fn find_terminal_ancestor(pid: u32, max_depth: usize) -> Option<u32> {
+    /// Find the terminal process in the ancestry of a given PID.
+    pub fn find_terminal_ancestor(&self, pid: u32, max_depth: usize) -> Option<u32> {
         let mut current_pid = pid;
         let mut depth = 0;
-        
+
         while depth < max_depth {
-            if let Some(parent_pid) = Self::get_parent_process_id(current_pid) {
+            if let Some(parent_pid) = self.get_parent_process_id(current_pid) {
                 debug!("Checking ancestor process PID: {} at depth {}", parent_pid, depth + 1);
-                
+
                 // Check if this is a terminal process
-                if let Some(info) = Self::get_process_info(parent_pid) {
+                if let Some(info) = self.get_process_info(parent_pid) {
                     let terminal_processes = vec![
                         "Terminal", "iTerm2", "alacritty", "kitty", "wezterm",
                         "gnome-terminal", "konsole", "xterm", "cmd.exe", "powershell.exe",
                         "WindowsTerminal.exe"
                     ];
-                    
+
                     if terminal_processes.iter().any(|&tp| info.name.contains(tp)) {
                         info!("Found terminal ancestor: {} (PID: {})", info.name, parent_pid);
                         return Some(parent_pid);
                     }
                 }
-                
+
                 current_pid = parent_pid;
                 depth += 1;
             } else {
                 break;
             }
         }
-        
+
         None
     }
 
-    #[cfg(target_os = "macos")]
-    fn get_parent_pid_macos(pid: u32) -> Option<u32> {
+    /// Direct parent-PID lookup on Windows.
+    ///
+    /// Opens the process with `PROCESS_QUERY_LIMITED_INFORMATION` and reads
+    /// `InheritedFromUniqueProcessId` from `ProcessBasicInformation` via
+    /// `NtQueryInformationProcess` — an O(1) query per PID instead of a full
+    /// Toolhelp snapshot scan. Falls back to the snapshot scan only when the
+    /// handle cannot be opened (e.g. elevated/protected processes returning
+    /// `ERROR_ACCESS_DENIED`).
+    #[cfg(windows)]
+    fn get_parent_pid_windows(pid: u32) -> Option<u32> {
         use std::mem;
-        use libc::{c_int, size_t, sysctl, CTL_KERN, KERN_PROC, KERN_PROC_PID};
-        
-        #[repr(C)]
-        struct kinfo_proc {
-            kp_proc: extern_proc,
-            kp_eproc: eproc,
-        }
-        
-        #[repr(C)]
-        struct extern_proc {
-            p_un: [u8; 16],
-            p_vmspace: u64,
-            p_sigacts: u64,
-            p_flag: i32,
-            p_stat: u8,
-            p_pid: i32,
-            p_oppid: i32,
-            p_dupfd: i32,
-            p_pgid: i32,
-            p_ppid: i32,
-            p_gid: i32,
-            p_comm: [u8; 17],
-            p_pgrp: u64,
-            p_addr: u64,
-            p_xstat: u16,
-            p_acflag: u16,
-            p_ru: u64,
-        }
-        
-        #[repr(C)]
-        struct eproc {
-            e_paddr: u64,
-            e_sess: u64,
-            e_pcred: pcred,
-            e_ucred: ucred,
-            e_vm: vmspace,
-            e_ppid: i32,
-            e_pgid: i32,
-            e_jobc: i16,
-            e_tdev: i32,
-            e_tpgid: i32,
-            e_tsess: u64,
-            e_wmesg: [u8; 8],
-            e_xsize: i64,
-            e_xrssize: i16,
-            e_xccount: i16,
-            e_xswrss: i16,
-            e_flag: i32,
-            e_login: [u8; 12],
-            e_spare: [i32; 4],
-        }
-        
-        #[repr(C)]
-        struct pcred {
-            pc_lock: [u8; 72],
-            pc_ucred: u64,
-            p_ruid: u32,
-            p_svuid: u32,
-            p_rgid: u32,
-            p_svgid: u32,
-            p_refcnt: i32,
-        }
-        
-        #[repr(C)]
-        struct ucred {
-            cr_ref: i32,
-            cr_uid: u32,
-            cr_ngroups: i16,
-            cr_groups: [u32; 16],
-        }
-        
-        #[repr(C)]
-        struct vmspace {
-            dummy: [u8; 32],
-        }
-        
-        let mut info: kinfo_proc = unsafe { mem::zeroed() };
-        let mut size = mem::size_of::<kinfo_proc>();
-        let mut mib = [CTL_KERN, KERN_PROC, KERN_PROC_PID, pid as c_int];
-        
-        let result = unsafe {
-            sysctl(
-                mib.as_mut_ptr(),
-                mib.len() as u32,
-                &mut info as *mut _ as *mut _,
-                &mut size as *mut _ as *mut size_t,
-                std::ptr::null_mut(),
-                0,
-            )
+        use windows::Wdk::System::Threading::{
+            NtQueryInformationProcess, ProcessBasicInformation, PROCESS_BASIC_INFORMATION,
         };
-        
-        if result == 0 && size > 0 {
-            Some(info.kp_eproc.e_ppid as u32)
-        } else {
-            None
-        }
-    }
+        use windows::Win32::Foundation::CloseHandle;
+        use windows::Win32::System::Threading::{OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION};
 
-    #[cfg(target_os = "macos")]
-    fn get_process_info_macos(pid: u32) -> Option<ProcessInfo> {
-        use std::process::Command;
-        
-        // Use ps command as a fallback for process info
-        match Command::new("ps")
-            .args(&["-p", &pid.to_string(), "-o", "ppid=,comm="])
-            .output()
-        {
-            Ok(output) => {
-                if output.status.success() {
-                    let output_str = String::from_utf8_lossy(&output.stdout);
-                    let parts: Vec<&str> = output_str.trim().split_whitespace().collect();
-                    if parts.len() >= 2 {
-                        let ppid = parts[0].parse::<u32>().unwrap_or(0);
-                        let name = parts[1..].join(" ");
-                        return Some(ProcessInfo { pid, ppid, name });
-                    }
-                }
-            }
-            Err(e) => {
-                warn!("Failed to run ps command: {}", e);
+        unsafe {
+            let handle = match OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) {
+                Ok(handle) => handle,
+                Err(_) => return Self::get_parent_pid_windows_snapshot(pid),
+            };
+
+            let mut info = PROCESS_BASIC_INFORMATION::default();
+            let mut return_len = 0u32;
+            let status = NtQueryInformationProcess(
+                handle,
+                ProcessBasicInformation,
+                &mut info as *mut _ as *mut _,
+                mem::size_of::<PROCESS_BASIC_INFORMATION>() as u32,
+                &mut return_len,
+            );
+
+            let _ = CloseHandle(handle);
+
+            if status.is_ok() {
+                Some(info.InheritedFromUniqueProcessId as u32)
+            } else {
+                None
             }
         }
-        
-        // Try to at least get parent PID
-        if let Some(ppid) = Self::get_parent_pid_macos(pid) {
-            Some(ProcessInfo {
-                pid,
-                ppid,
-                name: format!("Process {}", pid),
-            })
-        } else {
-            None
-        }
     }
 
-    #[cfg(target_os = "windows")]
-    fn get_parent_pid_windows(pid: u32) -> Option<u32> {
+    /// Toolhelp-snapshot fallback for [`Self::get_parent_pid_windows`].
+    #[cfg(windows)]
+    fn get_parent_pid_windows_snapshot(pid: u32) -> Option<u32> {
+        use windows::Win32::Foundation::CloseHandle;
         use windows::Win32::System::Diagnostics::ToolHelp::{
-            CreateToolhelp32Snapshot, Process32First, Process32Next, PROCESSENTRY32, TH32CS_SNAPPROCESS,
+            CreateToolhelp32Snapshot, Process32First, Process32Next, PROCESSENTRY32,
+            TH32CS_SNAPPROCESS,
         };
-        use windows::Win32::Foundation::HANDLE;
-        
+
         unsafe {
             let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0).ok()?;
-            
             let mut process_entry = PROCESSENTRY32 {
                 dwSize: std::mem::size_of::<PROCESSENTRY32>() as u32,
                 ..Default::default()
             };
-            
+
             if Process32First(snapshot, &mut process_entry).is_ok() {
                 loop {
                     if process_entry.th32ProcessID == pid {
-                        let _ = windows::Win32::Foundation::CloseHandle(snapshot);
-                        return Some(process_entry.th32ParentProcessID);
+                        let ppid = process_entry.th32ParentProcessID;
+                        let _ = CloseHandle(snapshot);
+                        return Some(ppid);
                     }
-                    
-                    if !Process32Next(snapshot, &mut process_entry).is_ok() {
+                    if Process32Next(snapshot, &mut process_entry).is_err() {
                         break;
                     }
                 }
             }
-            
-            let _ = windows::Win32::Foundation::CloseHandle(snapshot);
+            let _ = CloseHandle(snapshot);
         }
-        
+
         None
     }
 
-    #[cfg(target_os = "windows")]
-    fn get_process_info_windows(pid: u32) -> Option<ProcessInfo> {
-        use windows::Win32::System::Diagnostics::ToolHelp::{
-            CreateToolhelp32Snapshot, Process32First, Process32Next, PROCESSENTRY32, TH32CS_SNAPPROCESS,
+    /// Direct process-name lookup on Windows via `QueryFullProcessImageNameW`,
+    /// returning the final path component. Used when only the image name of a
+    /// single PID is needed, avoiding a full snapshot scan.
+    #[cfg(windows)]
+    pub fn process_name_windows(pid: u32) -> Option<String> {
+        use windows::core::PWSTR;
+        use windows::Win32::Foundation::CloseHandle;
+        use windows::Win32::System::Threading::{
+            OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_FORMAT,
+            PROCESS_QUERY_LIMITED_INFORMATION,
         };
-        
+
         unsafe {
-            let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0).ok()?;
-            
-            let mut process_entry = PROCESSENTRY32 {
-                dwSize: std::mem::size_of::<PROCESSENTRY32>() as u32,
-                ..Default::default()
-            };
-            
-            if Process32First(snapshot, &mut process_entry).is_ok() {
-                loop {
-                    if process_entry.th32ProcessID == pid {
-                        let name = String::from_utf16_lossy(
-                            &process_entry.szExeFile
-                                .iter()
-                                .take_while(|&&c| c != 0)
-                                .copied()
-                                .collect::<Vec<u16>>()
-                        );
-                        
-                        let _ = windows::Win32::Foundation::CloseHandle(snapshot);
-                        return Some(ProcessInfo {
-                            pid,
-                            ppid: process_entry.th32ParentProcessID,
-                            name,
-                        });
-                    }
-                    
-                    if !Process32Next(snapshot, &mut process_entry).is_ok() {
-                        break;
-                    }
-                }
-            }
-            
-            let _ = windows::Win32::Foundation::CloseHandle(snapshot);
-        }
-        
-        None
-    }
+            let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
 
-    #[cfg(target_os = "linux")]
-    fn get_parent_pid_linux(pid: u32) -> Option<u32> {
-        use std::fs;
-        
-        // Read /proc/[pid]/stat
-        let stat_path = format!("/proc/{}/stat", pid);
-        match fs::read_to_string(&stat_path) {
-            Ok(contents) => {
-                // Format: pid (comm) state ppid ...
-                // Find the closing parenthesis to skip the command name
-                if let Some(close_paren) = contents.rfind(')') {
-                    let after_name = &contents[close_paren + 1..];
-                    let fields: Vec<&str> = after_name.split_whitespace().collect();
-                    
-                    // ppid is the second field after the command name
-                    if fields.len() > 1 {
-                        return fields[1].parse::<u32>().ok();
-                    }
-                }
-            }
-            Err(e) => {
-                debug!("Failed to read {}: {}", stat_path, e);
+            let mut buffer = [0u16; 260];
+            let mut size = buffer.len() as u32;
+            let result = QueryFullProcessImageNameW(
+                handle,
+                PROCESS_NAME_FORMAT(0),
+                PWSTR(buffer.as_mut_ptr()),
+                &mut size,
+            );
+            let _ = CloseHandle(handle);
+
+            if result.is_err() {
+                return None;
             }
+
+            let full = String::from_utf16_lossy(&buffer[..size as usize]);
+            Some(
+                full.rsplit(['\\', '/'])
+                    .next()
+                    .unwrap_or(&full)
+                    .to_string(),
+            )
         }
-        
-        None
     }
+}
 
-    #[cfg(target_os = "linux")]
-    fn get_process_info_linux(pid: u32) -> Option<ProcessInfo> {
-        use std::fs;
-        
-        // Read /proc/[pid]/stat for ppid
-        let ppid = Self::get_parent_pid_linux(pid)?;
-        
-        // Read /proc/[pid]/comm for process name
-        let comm_path = format!("/proc/{}/comm", pid);
-        let name = match fs::read_to_string(&comm_path) {
-            Ok(contents) => contents.trim().to_string(),
-            Err(_) => format!("Process {}", pid),
-        };
-        
-        Some(ProcessInfo { pid, ppid, name })
+impl Default for ProcessTracker {
+    fn default() -> Self {
+        Self::new()
     }
 }
-
-// Platform-specific dependencies
-#[cfg(target_os = "macos")]
-extern crate libc;
\ No newline at end of file
@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
+use tokio::task::JoinHandle;
 use tracing::{debug, info, warn};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,15 +26,68 @@ pub struct WindowBounds {
     pub height: f64,
 }
 
+/// Broadcast when a watched session's root process exits.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionProcessExited {
+    pub session_id: String,
+    pub pid: u32,
+}
+
 pub struct WindowTracker {
     // Maps session IDs to their terminal window information
     session_window_map: Arc<RwLock<HashMap<String, WindowInfo>>>,
+    // Per-session exit-watcher tasks, so they can be torn down on unregister
+    watchers: Arc<RwLock<HashMap<String, JoinHandle<()>>>>,
+    // Fan-out of process-exit events to interested subscribers
+    exit_tx: broadcast::Sender<SessionProcessExited>,
 }
 
 impl WindowTracker {
     pub fn new() -> Self {
+        let (exit_tx, _) = broadcast::channel(64);
         Self {
             session_window_map: Arc::new(RwLock::new(HashMap::new())),
+            watchers: Arc::new(RwLock::new(HashMap::new())),
+            exit_tx,
+        }
+    }
+
+    /// Subscribe to [`SessionProcessExited`] events for all watched sessions.
+    pub fn subscribe_exits(&self) -> broadcast::Receiver<SessionProcessExited> {
+        self.exit_tx.subscribe()
+    }
+
+    /// Watch a session's root process and drop its window entry the moment the
+    /// process exits, rather than waiting for the next polled session diff.
+    ///
+    /// The wait is performed with a platform-native exit notification
+    /// (`pidfd_open` on Linux, a `kqueue` `EVFILT_PROC`/`NOTE_EXIT` filter on
+    /// macOS, `WaitForSingleObjectEx` on Windows) driven from a dedicated
+    /// blocking task. On exit the window is unregistered and a
+    /// [`SessionProcessExited`] event is broadcast. Any watcher already
+    /// registered for the session is replaced.
+    pub async fn watch_session(&self, session_id: String, pid: u32) {
+        let session_window_map = self.session_window_map.clone();
+        let watchers = self.watchers.clone();
+        let exit_tx = self.exit_tx.clone();
+        let watch_id = session_id.clone();
+
+        let handle = tokio::spawn(async move {
+            // The native wait is a blocking syscall; keep it off the runtime.
+            let _ = tokio::task::spawn_blocking(move || wait_for_exit(pid)).await;
+
+            if session_window_map.write().await.remove(&watch_id).is_some() {
+                info!("Session {} process {} exited, dropped window", watch_id, pid);
+            }
+            watchers.write().await.remove(&watch_id);
+            let _ = exit_tx.send(SessionProcessExited {
+                session_id: watch_id,
+                pid,
+            });
+        });
+
+        if let Some(previous) = self.watchers.write().await.insert(session_id, handle) {
+            previous.abort();
         }
     }
 
@@ -76,6 +130,9 @@ impl WindowTracker {
 
     /// Unregister a window for a session
     pub async fn unregister_window(&self, session_id: &str) {
+        if let Some(handle) = self.watchers.write().await.remove(session_id) {
+            handle.abort();
+        }
         if self.session_window_map.write().await.remove(session_id).is_some() {
             info!("Unregistered window for session: {}", session_id);
         }
@@ -153,55 +210,216 @@ impl WindowTracker {
         &self,
         terminal_app: &str,
         session_id: &str,
-        _tab_reference: &Option<String>,
-        _tab_id: &Option<String>,
+        tab_reference: &Option<String>,
+        tab_id: &Option<String>,
     ) -> Option<WindowInfo> {
         // Use macOS Core Graphics API to find windows
         // This is a simplified implementation - full version would use objc bindings
         let windows = self.get_all_terminal_windows_macos().await;
-        
-        for window in windows {
+
+        for mut window in windows {
             if window.terminal_app == terminal_app {
                 // Check if window title contains session ID
                 if let Some(title) = &window.title {
                     if title.contains(session_id) {
+                        // Core Graphics enumeration cannot see a terminal's tab
+                        // identifiers, so carry over the caller-supplied ones;
+                        // `focus_window_macos` relies on them to select the exact
+                        // tab rather than just activating the app.
+                        window.session_id = session_id.to_string();
+                        window.tab_reference = tab_reference.clone();
+                        window.tab_id = tab_id.clone();
                         return Some(window);
                     }
                 }
             }
         }
-        
+
         None
     }
 
     #[cfg(target_os = "macos")]
     async fn get_all_terminal_windows_macos(&self) -> Vec<WindowInfo> {
-        // This would use Core Graphics APIs via objc bindings
-        // For now, return empty as a placeholder
-        Vec::new()
+        use core_foundation::base::{CFType, TCFType};
+        use core_foundation::dictionary::CFDictionary;
+        use core_foundation::string::CFString;
+        use core_graphics::window::{
+            copy_window_info, kCGWindowBounds, kCGWindowName, kCGWindowNumber,
+            kCGWindowOwnerName, kCGWindowOwnerPID, kCGWindowListOptionOnScreenOnly,
+        };
+
+        // Owner names of the terminal apps we know how to focus.
+        const KNOWN_TERMINALS: &[&str] = &["Terminal", "iTerm2"];
+
+        let mut windows = Vec::new();
+
+        let list = match copy_window_info(kCGWindowListOptionOnScreenOnly, 0) {
+            Some(list) => list,
+            None => return windows,
+        };
+
+        for item in list.iter() {
+            // Each element is a CFDictionary describing one window.
+            let dict = unsafe {
+                CFDictionary::<CFString, CFType>::wrap_under_get_rule(*item as *const _)
+            };
+
+            let owner_name = Self::dict_string(&dict, unsafe { kCGWindowOwnerName });
+            let terminal_app = match owner_name {
+                Some(name) if KNOWN_TERMINALS.contains(&name.as_str()) => name,
+                _ => continue,
+            };
+
+            let window_id = Self::dict_number(&dict, unsafe { kCGWindowNumber })
+                .map(|n| n as u32)
+                .unwrap_or(0);
+            let owner_pid = Self::dict_number(&dict, unsafe { kCGWindowOwnerPID })
+                .map(|n| n as u32)
+                .unwrap_or(0);
+            let title = Self::dict_string(&dict, unsafe { kCGWindowName });
+            let bounds = Self::dict_bounds(&dict, unsafe { kCGWindowBounds });
+
+            windows.push(WindowInfo {
+                window_id,
+                owner_pid,
+                terminal_app,
+                session_id: String::new(),
+                created_at: String::new(),
+                tab_reference: None,
+                tab_id: None,
+                bounds,
+                title,
+            });
+        }
+
+        windows
+    }
+
+    #[cfg(target_os = "macos")]
+    fn dict_string(
+        dict: &core_foundation::dictionary::CFDictionary<
+            core_foundation::string::CFString,
+            core_foundation::base::CFType,
+        >,
+        key: core_foundation::string::CFStringRef,
+    ) -> Option<String> {
+        use core_foundation::base::TCFType;
+        use core_foundation::string::CFString;
+
+        let key = unsafe { CFString::wrap_under_get_rule(key) };
+        dict.find(&key)
+            .and_then(|value| value.downcast::<CFString>())
+            .map(|s| s.to_string())
+    }
+
+    #[cfg(target_os = "macos")]
+    fn dict_number(
+        dict: &core_foundation::dictionary::CFDictionary<
+            core_foundation::string::CFString,
+            core_foundation::base::CFType,
+        >,
+        key: core_foundation::string::CFStringRef,
+    ) -> Option<i64> {
+        use core_foundation::base::TCFType;
+        use core_foundation::number::CFNumber;
+        use core_foundation::string::CFString;
+
+        let key = unsafe { CFString::wrap_under_get_rule(key) };
+        dict.find(&key)
+            .and_then(|value| value.downcast::<CFNumber>())
+            .and_then(|n| n.to_i64())
+    }
+
+    #[cfg(target_os = "macos")]
+    fn dict_bounds(
+        dict: &core_foundation::dictionary::CFDictionary<
+            core_foundation::string::CFString,
+            core_foundation::base::CFType,
+        >,
+        key: core_foundation::string::CFStringRef,
+    ) -> Option<WindowBounds> {
+        use core_foundation::base::TCFType;
+        use core_foundation::dictionary::CFDictionary;
+        use core_foundation::number::CFNumber;
+        use core_foundation::string::CFString;
+
+        let key = unsafe { CFString::wrap_under_get_rule(key) };
+        let bounds_dict = dict
+            .find(&key)
+            .and_then(|value| value.downcast::<CFDictionary>())?;
+        // kCGWindowBounds is a serialized CGRect: X, Y, Width, Height.
+        let read = |name: &str| -> Option<f64> {
+            let k = CFString::new(name);
+            bounds_dict
+                .find(k.as_CFTypeRef() as *const _)
+                .map(|ptr| unsafe { CFNumber::wrap_under_get_rule(ptr as *const _) })
+                .and_then(|n| n.to_f64())
+        };
+
+        Some(WindowBounds {
+            x: read("X").unwrap_or(0.0),
+            y: read("Y").unwrap_or(0.0),
+            width: read("Width").unwrap_or(0.0),
+            height: read("Height").unwrap_or(0.0),
+        })
     }
 
     #[cfg(target_os = "macos")]
     async fn focus_window_macos(&self, window_info: &WindowInfo) -> Result<(), String> {
-        // Use AppleScript or Accessibility APIs to focus window
+        // Drive AppleScript to focus the exact window/tab rather than merely
+        // activating the app, using the tab identifiers captured at register
+        // time.
         use std::process::Command;
-        
-        let script = format!(
-            r#"tell application "{}" to activate"#,
-            window_info.terminal_app
-        );
-        
+
+        let script = match window_info.terminal_app.as_str() {
+            "Terminal" => {
+                if let Some(tab_ref) = &window_info.tab_reference {
+                    format!(
+                        r#"tell application "Terminal"
+    activate
+    set selected of {tab} to true
+    set frontmost of (first window whose tabs contains {tab}) to true
+end tell"#,
+                        tab = tab_ref
+                    )
+                } else {
+                    r#"tell application "Terminal" to activate"#.to_string()
+                }
+            }
+            "iTerm2" => {
+                if let Some(tab_id) = &window_info.tab_id {
+                    format!(
+                        r#"tell application "iTerm2"
+    activate
+    repeat with w in windows
+        repeat with t in tabs of w
+            if (id of t as string) is "{tab_id}" then
+                select w
+                select t
+            end if
+        end repeat
+    end repeat
+end tell"#,
+                        tab_id = tab_id
+                    )
+                } else {
+                    r#"tell application "iTerm2" to activate"#.to_string()
+                }
+            }
+            other => format!(r#"tell application "{}" to activate"#, other),
+        };
+
         let output = Command::new("osascript")
             .arg("-e")
             .arg(&script)
             .output()
             .map_err(|e| format!("Failed to run AppleScript: {}", e))?;
-            
+
         if !output.status.success() {
             let error = String::from_utf8_lossy(&output.stderr);
             return Err(format!("AppleScript failed: {}", error));
         }
-        
+
         Ok(())
     }
 
@@ -276,4 +494,72 @@ impl WindowTracker {
 
         None
     }
-}
\ No newline at end of file
+}
+
+/// Block until the process `pid` exits, using a platform-native notification.
+#[cfg(target_os = "linux")]
+fn wait_for_exit(pid: u32) {
+    unsafe {
+        let pidfd = libc::syscall(libc::SYS_pidfd_open, pid as libc::pid_t, 0);
+        if pidfd < 0 {
+            return;
+        }
+        let mut fds = libc::pollfd {
+            fd: pidfd as libc::c_int,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // A pidfd becomes readable when the process terminates.
+        loop {
+            let ret = libc::poll(&mut fds, 1, -1);
+            if ret < 0 && *libc::__errno_location() == libc::EINTR {
+                continue;
+            }
+            break;
+        }
+        libc::close(pidfd as libc::c_int);
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn wait_for_exit(pid: u32) {
+    unsafe {
+        let kq = libc::kqueue();
+        if kq < 0 {
+            return;
+        }
+        let mut change: libc::kevent = std::mem::zeroed();
+        change.ident = pid as libc::uintptr_t;
+        change.filter = libc::EVFILT_PROC;
+        change.flags = libc::EV_ADD | libc::EV_ONESHOT;
+        change.fflags = libc::NOTE_EXIT;
+
+        let mut event: libc::kevent = std::mem::zeroed();
+        // Register the filter, then block in a second kevent call for the event.
+        if libc::kevent(kq, &change, 1, std::ptr::null_mut(), 0, std::ptr::null()) != -1 {
+            libc::kevent(kq, std::ptr::null(), 0, &mut event, 1, std::ptr::null());
+        }
+        libc::close(kq);
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn wait_for_exit(pid: u32) {
+    use windows::Win32::Foundation::{CloseHandle, WAIT_FAILED};
+    use windows::Win32::System::Threading::{
+        OpenProcess, WaitForSingleObjectEx, INFINITE, PROCESS_SYNCHRONIZE,
+    };
+
+    unsafe {
+        let handle = match OpenProcess(PROCESS_SYNCHRONIZE, false, pid) {
+            Ok(handle) => handle,
+            Err(_) => return,
+        };
+        let result = WaitForSingleObjectEx(handle, INFINITE, false);
+        let _ = CloseHandle(handle);
+        let _ = result == WAIT_FAILED;
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn wait_for_exit(_pid: u32) {}
\ No newline at end of file
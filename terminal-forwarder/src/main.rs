@@ -1,22 +1,384 @@
-use axum::{routing::{post, get, delete}, Router, Json, extract::{Path, State}};
+use axum::{
+    extract::{ws::{Message, WebSocket, WebSocketUpgrade}, Extension, Path, Query, State},
+    http::{header::HeaderMap, HeaderValue, Request, StatusCode},
+    middleware::{self, Next},
+    response::Response,
+    routing::{delete, get, post},
+    Json, Router,
+};
 use portable_pty::{native_pty_system, CommandBuilder, PtySize};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::{Read, Write};
 use std::sync::{Arc, Mutex};
-use tokio::sync::Mutex as TokioMutex;
+use tokio::sync::broadcast;
 use uuid::Uuid;
 
+/// Capacity of each session's live broadcast channel, in chunks.
+const BROADCAST_CAPACITY: usize = 1024;
+/// Retained scrollback per session, in bytes, for late-joining subscribers.
+const RING_CAPACITY_BYTES: usize = 256 * 1024;
+/// Retained scrollback per session, in lines.
+const RING_CAPACITY_LINES: usize = 10_000;
+
 #[derive(Clone)]
 struct AppState {
     manager: Arc<TerminalManager>,
+    auth: Arc<Auth>,
+}
+
+/// Bearer-token registry used by the auth middleware. Tokens are read from the
+/// environment at startup: `VT_ADMIN_TOKEN` grants an admin token that can see
+/// and act on every session, and `VT_TOKENS` is a comma-separated list of
+/// ordinary tokens, each scoped to the sessions it creates.
+struct Auth {
+    admin: Option<String>,
+    tokens: HashSet<String>,
+}
+
+impl Auth {
+    /// Build the token registry from `VT_ADMIN_TOKEN`/`VT_TOKENS`.
+    fn from_env() -> Self {
+        let admin = std::env::var("VT_ADMIN_TOKEN").ok().filter(|t| !t.is_empty());
+        let tokens = std::env::var("VT_TOKENS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(str::to_string)
+            .collect();
+        Self { admin, tokens }
+    }
+
+    /// Resolve a presented bearer token to an [`Identity`], or `None` if it is
+    /// not a known admin or ordinary token.
+    fn identify(&self, token: &str) -> Option<Identity> {
+        if self.admin.as_deref() == Some(token) {
+            Some(Identity { token: token.to_string(), admin: true })
+        } else if self.tokens.contains(token) {
+            Some(Identity { token: token.to_string(), admin: false })
+        } else {
+            None
+        }
+    }
+}
+
+/// Bounded retained-output buffer so a subscriber that joins late (or polls
+/// `/read`) still sees recent terminal output instead of only bytes produced
+/// after it connected.
+///
+/// Every byte the session ever produces is assigned a monotonically increasing
+/// sequence number; the buffer keeps the most recent bytes up to its byte/line
+/// caps. A reconnecting client passes the last sequence it saw and gets exactly
+/// the bytes it missed (or as many as are still retained).
+struct RingBuffer {
+    data: std::collections::VecDeque<u8>,
+    byte_cap: usize,
+    line_cap: usize,
+    /// Sequence number of `data.front()`.
+    start_seq: u64,
+}
+
+impl RingBuffer {
+    fn new(byte_cap: usize, line_cap: usize) -> Self {
+        Self {
+            data: std::collections::VecDeque::new(),
+            byte_cap,
+            line_cap,
+            start_seq: 0,
+        }
+    }
+
+    /// Sequence number one past the last retained byte (the current tail).
+    fn end_seq(&self) -> u64 {
+        self.start_seq + self.data.len() as u64
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        self.data.extend(bytes.iter().copied());
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.data.len() > self.byte_cap {
+            self.data.pop_front();
+            self.start_seq += 1;
+        }
+        let mut lines = self.data.iter().filter(|&&b| b == b'\n').count();
+        while lines > self.line_cap {
+            if let Some(byte) = self.data.pop_front() {
+                self.start_seq += 1;
+                if byte == b'\n' {
+                    lines -= 1;
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Bytes retained at or after `since`, plus the tail sequence number to
+    /// resume from. When `since` is `None` (or older than what we retain) the
+    /// full retained buffer is returned.
+    fn since(&self, since: Option<u64>) -> (Vec<u8>, u64) {
+        let end = self.end_seq();
+        let from = match since {
+            Some(seq) if seq >= end => return (Vec::new(), end),
+            Some(seq) if seq > self.start_seq => (seq - self.start_seq) as usize,
+            _ => 0,
+        };
+        (self.data.iter().skip(from).copied().collect(), end)
+    }
+}
+
+/// How and where to open a PTY. `ssh` being `None` means a local terminal.
+struct PtySpec {
+    command: Vec<String>,
+    cwd: Option<String>,
+    cols: u16,
+    rows: u16,
+    ssh: Option<SshConfig>,
+    /// Retained scrollback caps for this session's ring buffer.
+    scrollback_bytes: usize,
+    scrollback_lines: usize,
+    /// Record the session to an asciinema cast file from the start.
+    record: bool,
+    /// Path used for the recording (derived from the session id).
+    recording_path: std::path::PathBuf,
+}
+
+/// Connection parameters for a remote (SSH) terminal.
+#[derive(Debug, Clone, Deserialize)]
+struct SshConfig {
+    host: String,
+    user: Option<String>,
+    identity: Option<String>,
+}
+
+/// Final disposition of a session's child process.
+#[derive(Debug, Clone, Serialize)]
+struct ExitStatus {
+    /// Exit code reported by the child. `portable_pty`/`wezterm_ssh` surface a
+    /// signalled exit only folded into this code (128 + signal on Unix), so a
+    /// separate signal field cannot be populated faithfully and is omitted.
+    /// SSH sessions do not read the remote exit code and always leave this
+    /// `None`.
+    code: Option<i32>,
+    /// Unix timestamp (seconds) at which the exit was observed.
+    exited_at: u64,
+}
+
+/// Tracks when a session's child process terminates and wakes any waiters.
+struct ExitTracker {
+    status: Mutex<Option<ExitStatus>>,
+    notify: tokio::sync::Notify,
+}
+
+impl ExitTracker {
+    fn new() -> Arc<Self> {
+        Arc::new(Self {
+            status: Mutex::new(None),
+            notify: tokio::sync::Notify::new(),
+        })
+    }
+
+    /// Record the exit and wake every waiter. First observation wins.
+    fn finish(&self, status: ExitStatus) {
+        let mut slot = self.status.lock().unwrap();
+        if slot.is_none() {
+            *slot = Some(status);
+            self.notify.notify_waiters();
+        }
+    }
+
+    fn get(&self) -> Option<ExitStatus> {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Resolve once the process has terminated.
+    async fn wait(&self) -> ExitStatus {
+        loop {
+            let notified = self.notify.notified();
+            if let Some(status) = self.get() {
+                return status;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// Directory holding recorded asciinema cast files.
+const RECORDINGS_DIR: &str = "recordings";
+
+/// Writes a session's output, resize and timing events to an asciinema v2
+/// `.cast` file: a JSON header line followed by one JSON array per event.
+struct Recorder {
+    inner: Mutex<std::io::BufWriter<std::fs::File>>,
+    start: std::time::Instant,
+    path: std::path::PathBuf,
+    /// Trailing bytes of an incomplete multi-byte UTF-8 sequence, held back
+    /// until the rest of the code point arrives in a later chunk.
+    pending: Mutex<Vec<u8>>,
+}
+
+impl Recorder {
+    /// Create a cast file and write its v2 header.
+    fn create(path: std::path::PathBuf, cols: u16, rows: u16) -> Result<Arc<Self>, String> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("recording dir failed: {e}"))?;
+        }
+        let file = std::fs::File::create(&path).map_err(|e| format!("recording create failed: {e}"))?;
+        let mut writer = std::io::BufWriter::new(file);
+        let header = serde_json::json!({
+            "version": 2,
+            "width": cols,
+            "height": rows,
+            "timestamp": now_unix(),
+        });
+        writeln!(writer, "{header}").map_err(|e| format!("recording write failed: {e}"))?;
+        writer.flush().map_err(|e| format!("recording flush failed: {e}"))?;
+        Ok(Arc::new(Self {
+            inner: Mutex::new(writer),
+            start: std::time::Instant::now(),
+            path,
+            pending: Mutex::new(Vec::new()),
+        }))
+    }
+
+    fn record_output(&self, data: &[u8]) {
+        // 4 KiB read chunks split multi-byte code points at arbitrary
+        // boundaries; decode only the complete prefix and carry any incomplete
+        // trailing sequence over to the next chunk so valid UTF-8 output is
+        // never mangled into replacement characters.
+        let mut pending = self.pending.lock().unwrap();
+        pending.extend_from_slice(data);
+
+        let mut text = String::new();
+        loop {
+            match std::str::from_utf8(&pending) {
+                Ok(s) => {
+                    text.push_str(s);
+                    pending.clear();
+                    break;
+                }
+                Err(e) => {
+                    let valid = e.valid_up_to();
+                    text.push_str(&String::from_utf8_lossy(&pending[..valid]));
+                    match e.error_len() {
+                        // Incomplete tail: hold it back for the next chunk.
+                        None => {
+                            pending.drain(..valid);
+                            break;
+                        }
+                        // Genuinely invalid bytes: emit one replacement and skip.
+                        Some(bad) => {
+                            text.push('\u{FFFD}');
+                            pending.drain(..valid + bad);
+                        }
+                    }
+                }
+            }
+        }
+
+        if !text.is_empty() {
+            self.write_event(&serde_json::json!([self.elapsed(), "o", text]));
+        }
+    }
+
+    fn record_resize(&self, cols: u16, rows: u16) {
+        let dims = format!("{cols}x{rows}");
+        self.write_event(&serde_json::json!([self.elapsed(), "r", dims]));
+    }
+
+    fn elapsed(&self) -> f64 {
+        self.start.elapsed().as_secs_f64()
+    }
+
+    fn write_event(&self, event: &serde_json::Value) {
+        let mut writer = self.inner.lock().unwrap();
+        let _ = writeln!(writer, "{event}");
+        let _ = writer.flush();
+    }
+}
+
+/// Seconds since the Unix epoch.
+fn now_unix() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// A running PTY, local or remote, behind a uniform interface. Implementations
+/// own the child process and the reader pump that publishes output into a
+/// broadcast channel plus a retained ring buffer.
+trait PtyBackend: Send + Sync {
+    fn write(&self, data: &[u8]) -> Result<(), String>;
+    fn resize(&self, cols: u16, rows: u16) -> Result<(), String>;
+    /// Retained scrollback from `since` plus a live cursor onto new output, and
+    /// the tail sequence number the replay ends at.
+    fn subscribe(&self, since: Option<u64>) -> (Vec<u8>, u64, broadcast::Receiver<Vec<u8>>);
+    /// Retained scrollback from `since` (for the polling `/read` route), with
+    /// the tail sequence number.
+    fn snapshot(&self, since: Option<u64>) -> (Vec<u8>, u64);
+    fn kill(&self) -> Result<(), String>;
+    /// Exit tracker for the backend's child process.
+    fn exit(&self) -> Arc<ExitTracker>;
+    /// Begin recording this session to `path`. Defaults to unsupported.
+    fn start_recording(&self, _path: std::path::PathBuf) -> Result<(), String> {
+        Err("recording not supported for this session".to_string())
+    }
+    /// Path of the active recording, if any.
+    fn recording_path(&self) -> Option<std::path::PathBuf> {
+        None
+    }
+}
+
+/// Metadata tracked centrally for each live session.
+struct SessionMeta {
+    pid: u32,
+    command: Vec<String>,
+    cwd: Option<String>,
+    created_at: u64,
+    /// Non-secret owner label of the client that created the session (a
+    /// fingerprint of its bearer token — never the token itself).
+    owner: String,
 }
 
 struct Session {
-    writer: Box<dyn Write + Send>,
-    output: Arc<TokioMutex<Vec<u8>>>,
-    _child: Box<dyn portable_pty::Child + Send + Sync>,
-    pty_pair: portable_pty::PtyPair,
+    backend: Box<dyn PtyBackend>,
+    meta: SessionMeta,
+}
+
+/// The authenticated caller, derived from the bearer token by the auth
+/// middleware. `admin` callers may act on any session.
+#[derive(Clone)]
+struct Identity {
+    token: String,
+    admin: bool,
+}
+
+impl Identity {
+    /// Stable, non-secret owner label safe to expose over the API. Derived from
+    /// the bearer token by fingerprinting it so the raw token is never stored on
+    /// a session or echoed back by the listing route.
+    fn owner(&self) -> String {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.token.hash(&mut hasher);
+        format!("u-{:016x}", hasher.finish())
+    }
+}
+
+/// Summary of a session returned by the listing route.
+#[derive(Debug, Serialize)]
+struct SessionInfo {
+    id: String,
+    pid: u32,
+    command: Vec<String>,
+    cwd: Option<String>,
+    created_at: u64,
+    owner: String,
 }
 
 struct TerminalManager {
@@ -36,30 +398,235 @@ impl TerminalManager {
         cwd: Option<String>,
         cols: Option<u16>,
         rows: Option<u16>,
+        ssh: Option<SshConfig>,
+        scrollback_bytes: Option<usize>,
+        scrollback_lines: Option<usize>,
+        record: bool,
+        owner: &Identity,
     ) -> Result<(String, u32), String> {
         let id = Uuid::new_v4().to_string();
+        let command_meta = command.clone();
+        let cwd_meta = cwd.clone();
+        let spec = PtySpec {
+            command,
+            cwd,
+            cols: cols.unwrap_or(80),
+            rows: rows.unwrap_or(24),
+            ssh,
+            scrollback_bytes: scrollback_bytes.unwrap_or(RING_CAPACITY_BYTES),
+            scrollback_lines: scrollback_lines.unwrap_or(RING_CAPACITY_LINES),
+            record,
+            recording_path: std::path::Path::new(RECORDINGS_DIR).join(format!("{id}.cast")),
+        };
+
+        let (backend, pid): (Box<dyn PtyBackend>, u32) = match spec.ssh.is_some() {
+            true => {
+                let (backend, pid) = SshPtyBackend::open(spec)?;
+                (Box::new(backend), pid)
+            }
+            false => {
+                let (backend, pid) = LocalPtyBackend::open(spec)?;
+                (Box::new(backend), pid)
+            }
+        };
+
+        let session = Session {
+            backend,
+            meta: SessionMeta {
+                pid,
+                command: command_meta,
+                cwd: cwd_meta,
+                created_at: now_unix(),
+                owner: owner.owner(),
+            },
+        };
+        self.sessions.lock().unwrap().insert(id.clone(), session);
+        Ok((id, pid))
+    }
+
+    /// Look up a session, enforcing that `identity` owns it (or is an admin).
+    fn authorize<'a>(
+        sessions: &'a HashMap<String, Session>,
+        id: &str,
+        identity: &Identity,
+    ) -> Result<&'a Session, String> {
+        let session = sessions.get(id).ok_or_else(|| "session not found".to_string())?;
+        if identity.admin || session.meta.owner == identity.owner() {
+            Ok(session)
+        } else {
+            Err("forbidden".to_string())
+        }
+    }
+
+    fn write(&self, id: &str, data: &[u8], identity: &Identity) -> Result<(), String> {
+        let sessions = self.sessions.lock().unwrap();
+        Self::authorize(&sessions, id, identity)?.backend.write(data)
+    }
+
+    fn resize(&self, id: &str, cols: u16, rows: u16, identity: &Identity) -> Result<(), String> {
+        let sessions = self.sessions.lock().unwrap();
+        Self::authorize(&sessions, id, identity)?
+            .backend
+            .resize(cols, rows)
+    }
+
+    /// Incremental poll of a session's output, non-destructive so N clients can
+    /// all read the same session. A caller passes the `seq` it last saw and gets
+    /// exactly the bytes produced since, along with the new tail `seq` to resume
+    /// from. With no `seq` the read starts from the current tail (`tail -f`
+    /// semantics) and returns no backlog, so a polling client never re-receives
+    /// the whole scrollback on every call; pass `seq=0` to fetch scrollback.
+    fn read(&self, id: &str, since: Option<u64>, identity: &Identity) -> Result<(Vec<u8>, u64), String> {
+        let sessions = self.sessions.lock().unwrap();
+        let (data, end) = Self::authorize(&sessions, id, identity)?.backend.snapshot(since);
+        match since {
+            Some(_) => Ok((data, end)),
+            None => Ok((Vec::new(), end)),
+        }
+    }
+
+    /// Obtain a live subscriber cursor plus the retained scrollback from
+    /// `since`, so a reconnecting viewer can replay what it missed before
+    /// switching to live streaming.
+    fn subscribe(&self, id: &str, since: Option<u64>, identity: &Identity) -> Result<(Vec<u8>, u64, broadcast::Receiver<Vec<u8>>), String> {
+        let sessions = self.sessions.lock().unwrap();
+        Ok(Self::authorize(&sessions, id, identity)?.backend.subscribe(since))
+    }
+
+    fn kill(&self, id: &str, identity: &Identity) -> Result<(), String> {
+        let mut sessions = self.sessions.lock().unwrap();
+        // Authorize before removing so a non-owner cannot evict the session.
+        Self::authorize(&sessions, id, identity)?;
+        if let Some(session) = sessions.remove(id) {
+            session.backend.kill()?;
+        }
+        Ok(())
+    }
+
+    /// Current running/exited status of a session.
+    fn status(&self, id: &str, identity: &Identity) -> Result<StatusResponse, String> {
+        let sessions = self.sessions.lock().unwrap();
+        let exit = Self::authorize(&sessions, id, identity)?.backend.exit().get();
+        Ok(StatusResponse {
+            running: exit.is_none(),
+            exit,
+        })
+    }
+
+    /// Exit tracker for a session, so a waiter can await termination without
+    /// holding the sessions lock.
+    fn exit_tracker(&self, id: &str, identity: &Identity) -> Result<Arc<ExitTracker>, String> {
+        let sessions = self.sessions.lock().unwrap();
+        Ok(Self::authorize(&sessions, id, identity)?.backend.exit())
+    }
+
+    /// Begin recording a live session to a cast file derived from its id.
+    fn start_recording(&self, id: &str, identity: &Identity) -> Result<(), String> {
+        let sessions = self.sessions.lock().unwrap();
+        let path = std::path::Path::new(RECORDINGS_DIR).join(format!("{id}.cast"));
+        Self::authorize(&sessions, id, identity)?
+            .backend
+            .start_recording(path)
+    }
+
+    /// Read the finished cast file for a session.
+    fn recording(&self, id: &str, identity: &Identity) -> Result<Vec<u8>, String> {
+        let path = {
+            let sessions = self.sessions.lock().unwrap();
+            Self::authorize(&sessions, id, identity)?
+                .backend
+                .recording_path()
+                .ok_or_else(|| "session is not being recorded".to_string())?
+        };
+        std::fs::read(&path).map_err(|e| format!("recording read failed: {e}"))
+    }
+
+    /// Replay a stored cast as a new session streamed with original timing.
+    fn play_recording(&self, name: &str, owner: &Identity) -> Result<(String, u32), String> {
+        // `name` comes from the URL path; keep it to a single bare file stem so
+        // a caller cannot escape `RECORDINGS_DIR` with separators or `..`.
+        let valid = !name.is_empty()
+            && name != "."
+            && name != ".."
+            && !name.contains(['/', '\\'])
+            && !name.contains('\0');
+        if !valid {
+            return Err("invalid recording name".to_string());
+        }
+        let path = std::path::Path::new(RECORDINGS_DIR).join(format!("{name}.cast"));
+        let id = Uuid::new_v4().to_string();
+        let backend = PlaybackBackend::open(path)?;
+        let session = Session {
+            backend: Box::new(backend),
+            meta: SessionMeta {
+                pid: 0,
+                command: vec![format!("replay:{name}")],
+                cwd: None,
+                created_at: now_unix(),
+                owner: owner.owner(),
+            },
+        };
+        self.sessions.lock().unwrap().insert(id.clone(), session);
+        Ok((id, 0))
+    }
+
+    /// List sessions visible to `identity`: its own, or all for an admin.
+    fn list(&self, identity: &Identity) -> Vec<SessionInfo> {
+        let sessions = self.sessions.lock().unwrap();
+        sessions
+            .iter()
+            .filter(|(_, session)| identity.admin || session.meta.owner == identity.owner())
+            .map(|(id, session)| SessionInfo {
+                id: id.clone(),
+                pid: session.meta.pid,
+                command: session.meta.command.clone(),
+                cwd: session.meta.cwd.clone(),
+                created_at: session.meta.created_at,
+                owner: session.meta.owner.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Local PTY backed by `portable_pty`.
+struct LocalPtyBackend {
+    writer: Mutex<Box<dyn Write + Send>>,
+    output_tx: broadcast::Sender<Vec<u8>>,
+    ring: Arc<Mutex<RingBuffer>>,
+    killer: Mutex<Box<dyn portable_pty::ChildKiller + Send + Sync>>,
+    exit: Arc<ExitTracker>,
+    pty_pair: portable_pty::PtyPair,
+    /// Active recorder, shared with the reader thread. `None` when not recording.
+    recorder: Arc<Mutex<Option<Arc<Recorder>>>>,
+    /// Last known terminal size, for starting a recording after creation.
+    size: Mutex<(u16, u16)>,
+}
+
+impl LocalPtyBackend {
+    fn open(spec: PtySpec) -> Result<(Self, u32), String> {
         let pty_system = native_pty_system();
         let pair = pty_system
             .openpty(PtySize {
-                rows: rows.unwrap_or(24),
-                cols: cols.unwrap_or(80),
+                rows: spec.rows,
+                cols: spec.cols,
                 pixel_width: 0,
                 pixel_height: 0,
             })
             .map_err(|e| format!("openpty failed: {e}"))?;
 
-        let mut cmd = CommandBuilder::new(&command[0]);
-        if command.len() > 1 {
-            cmd.args(&command[1..]);
+        let mut cmd = CommandBuilder::new(&spec.command[0]);
+        if spec.command.len() > 1 {
+            cmd.args(&spec.command[1..]);
         }
-        if let Some(cwd) = &cwd {
+        if let Some(cwd) = &spec.cwd {
             cmd.cwd(cwd);
         }
-        let child = pair
+        let mut child = pair
             .slave
             .spawn_command(cmd)
             .map_err(|e| format!("spawn failed: {e}"))?;
         let pid = child.process_id().unwrap_or(0);
+        let killer = child.clone_killer();
 
         let mut reader = pair
             .master
@@ -70,51 +637,90 @@ impl TerminalManager {
             .take_writer()
             .map_err(|e| format!("take writer failed: {e}"))?;
 
-        let output = Arc::new(TokioMutex::new(Vec::new()));
-        let output_clone = output.clone();
+        let (output_tx, _) = broadcast::channel(BROADCAST_CAPACITY);
+        let ring = Arc::new(Mutex::new(RingBuffer::new(
+            spec.scrollback_bytes,
+            spec.scrollback_lines,
+        )));
+        let exit = ExitTracker::new();
+
+        // Optionally start recording immediately.
+        let recorder: Arc<Mutex<Option<Arc<Recorder>>>> = Arc::new(Mutex::new(None));
+        if spec.record {
+            let rec = Recorder::create(spec.recording_path.clone(), spec.cols, spec.rows)?;
+            *recorder.lock().unwrap() = Some(rec);
+        }
+
+        let tx_clone = output_tx.clone();
+        let ring_clone = ring.clone();
+        let recorder_clone = recorder.clone();
         std::thread::spawn(move || {
             let mut buf = [0u8; 4096];
             loop {
                 match reader.read(&mut buf) {
                     Ok(0) => break,
                     Ok(n) => {
-                        let mut out = futures::executor::block_on(output_clone.lock());
-                        out.extend_from_slice(&buf[..n]);
+                        let chunk = buf[..n].to_vec();
+                        if let Some(rec) = recorder_clone.lock().unwrap().as_ref() {
+                            rec.record_output(&chunk);
+                        }
+                        // Push into the ring and fan out to live subscribers
+                        // while holding the ring lock, so it is atomic against
+                        // `subscribe()` (which snapshots the ring and subscribes
+                        // under the same lock). This closes the gap where a
+                        // chunk produced between a late joiner's snapshot and its
+                        // subscribe would land in neither and be dropped. A send
+                        // error only means there are no live subscribers; the
+                        // ring still retains the data for later joiners.
+                        let mut ring = ring_clone.lock().unwrap();
+                        ring.push(&chunk);
+                        let _ = tx_clone.send(chunk);
                     }
                     Err(_) => break,
                 }
             }
         });
 
-        let session = Session {
-            writer,
-            output,
-            _child: child,
-            pty_pair: pair,
-        };
-        self.sessions.lock().unwrap().insert(id.clone(), session);
-        Ok((id, pid))
+        // Reap the OS child and record its exit status, whether it exits on its
+        // own or is killed. The `Session` is deliberately left in the map after
+        // the child dies so `/status` and `/wait` stay answerable; the client
+        // drops it with `DELETE /sessions/:id` once it has observed the exit.
+        let exit_clone = exit.clone();
+        std::thread::spawn(move || {
+            let status = child.wait().ok();
+            exit_clone.finish(ExitStatus {
+                code: status.map(|s| s.exit_code() as i32),
+                exited_at: now_unix(),
+            });
+        });
+
+        Ok((
+            Self {
+                writer: Mutex::new(writer),
+                output_tx,
+                ring,
+                killer: Mutex::new(killer),
+                exit,
+                pty_pair: pair,
+                size: Mutex::new((spec.cols, spec.rows)),
+                recorder,
+            },
+            pid,
+        ))
     }
+}
 
-    fn write(&self, id: &str, data: &[u8]) -> Result<(), String> {
-        let mut sessions = self.sessions.lock().unwrap();
-        let session = sessions.get_mut(id).ok_or_else(|| "session not found".to_string())?;
-        session
-            .writer
+impl PtyBackend for LocalPtyBackend {
+    fn write(&self, data: &[u8]) -> Result<(), String> {
+        let mut writer = self.writer.lock().unwrap();
+        writer
             .write_all(data)
             .map_err(|e| format!("write failed: {e}"))?;
-        session
-            .writer
-            .flush()
-            .map_err(|e| format!("flush failed: {e}"))?;
-        Ok(())
+        writer.flush().map_err(|e| format!("flush failed: {e}"))
     }
 
-    fn resize(&self, id: &str, cols: u16, rows: u16) -> Result<(), String> {
-        let sessions = self.sessions.lock().unwrap();
-        let session = sessions.get(id).ok_or_else(|| "session not found".to_string())?;
-        session
-            .pty_pair
+    fn resize(&self, cols: u16, rows: u16) -> Result<(), String> {
+        self.pty_pair
             .master
             .resize(PtySize {
                 cols,
@@ -122,24 +728,386 @@ impl TerminalManager {
                 pixel_width: 0,
                 pixel_height: 0,
             })
-            .map_err(|e| format!("resize failed: {e}"))?
-            ;
+            .map_err(|e| format!("resize failed: {e}"))?;
+        *self.size.lock().unwrap() = (cols, rows);
+        if let Some(rec) = self.recorder.lock().unwrap().as_ref() {
+            rec.record_resize(cols, rows);
+        }
         Ok(())
     }
 
-    fn read(&self, id: &str) -> Result<Vec<u8>, String> {
-        let sessions = self.sessions.lock().unwrap();
-        let session = sessions.get(id).ok_or_else(|| "session not found".to_string())?;
-        let mut output = futures::executor::block_on(session.output.lock());
-        let data = output.split_off(0);
-        Ok(data)
+    fn subscribe(&self, since: Option<u64>) -> (Vec<u8>, u64, broadcast::Receiver<Vec<u8>>) {
+        // Snapshot the ring and subscribe under the same lock the reader holds
+        // for push+send, so the replay-to-live handoff has no gap.
+        let ring = self.ring.lock().unwrap();
+        let (backlog, next_seq) = ring.since(since);
+        let rx = self.output_tx.subscribe();
+        (backlog, next_seq, rx)
     }
 
-    fn kill(&self, id: &str) -> Result<(), String> {
-        let mut sessions = self.sessions.lock().unwrap();
-        sessions.remove(id);
+    fn snapshot(&self, since: Option<u64>) -> (Vec<u8>, u64) {
+        self.ring.lock().unwrap().since(since)
+    }
+
+    fn start_recording(&self, path: std::path::PathBuf) -> Result<(), String> {
+        let (cols, rows) = *self.size.lock().unwrap();
+        let rec = Recorder::create(path, cols, rows)?;
+        *self.recorder.lock().unwrap() = Some(rec);
+        Ok(())
+    }
+
+    fn recording_path(&self) -> Option<std::path::PathBuf> {
+        self.recorder
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|r| r.path.clone())
+    }
+
+    fn kill(&self) -> Result<(), String> {
+        self.killer
+            .lock()
+            .unwrap()
+            .kill()
+            .map_err(|e| format!("kill failed: {e}"))
+    }
+
+    fn exit(&self) -> Arc<ExitTracker> {
+        self.exit.clone()
+    }
+}
+
+/// Commands sent to the thread driving a remote SSH session.
+enum SshCommand {
+    Write(Vec<u8>),
+    Resize(u16, u16),
+    Kill,
+}
+
+/// Remote PTY opened over SSH with `wezterm_ssh`. A dedicated thread owns the
+/// ssh session, pumps the remote child's combined output into the same
+/// broadcast/ring path as the local backend, and applies write/resize/kill
+/// commands delivered over a channel.
+struct SshPtyBackend {
+    input_tx: std::sync::mpsc::Sender<SshCommand>,
+    output_tx: broadcast::Sender<Vec<u8>>,
+    ring: Arc<Mutex<RingBuffer>>,
+    exit: Arc<ExitTracker>,
+    recorder: Arc<Mutex<Option<Arc<Recorder>>>>,
+    size: Mutex<(u16, u16)>,
+}
+
+impl SshPtyBackend {
+    fn open(spec: PtySpec) -> Result<(Self, u32), String> {
+        use wezterm_ssh::{Config, Session as SshSession};
+
+        let ssh = spec.ssh.clone().expect("ssh config present for ssh backend");
+
+        let mut config = Config::new().map_err(|e| format!("ssh config failed: {e}"))?;
+        config.add_default_config_files();
+        let mut opts = config.for_host(&ssh.host);
+        if let Some(user) = &ssh.user {
+            opts.insert("user".to_string(), user.clone());
+        }
+        if let Some(identity) = &ssh.identity {
+            opts.insert("identityfile".to_string(), identity.clone());
+        }
+
+        let (output_tx, _) = broadcast::channel(BROADCAST_CAPACITY);
+        let ring = Arc::new(Mutex::new(RingBuffer::new(
+            spec.scrollback_bytes,
+            spec.scrollback_lines,
+        )));
+        let exit = ExitTracker::new();
+        let (input_tx, input_rx) = std::sync::mpsc::channel::<SshCommand>();
+
+        let recorder: Arc<Mutex<Option<Arc<Recorder>>>> = Arc::new(Mutex::new(None));
+        if spec.record {
+            let rec = Recorder::create(spec.recording_path.clone(), spec.cols, spec.rows)?;
+            *recorder.lock().unwrap() = Some(rec);
+        }
+
+        let command_line = spec.command.join(" ");
+        let size = PtySize {
+            cols: spec.cols,
+            rows: spec.rows,
+            pixel_width: 0,
+            pixel_height: 0,
+        };
+
+        let tx_clone = output_tx.clone();
+        let ring_clone = ring.clone();
+        let exit_clone = exit.clone();
+        let exit_reader = exit.clone();
+        let recorder_reader = recorder.clone();
+        let recorder_resize = recorder.clone();
+        std::thread::spawn(move || {
+            futures::executor::block_on(async move {
+                let (session, events) = match SshSession::connect(opts) {
+                    Ok(pair) => pair,
+                    Err(e) => {
+                        let _ = tx_clone.send(format!("ssh connect failed: {e}\n").into_bytes());
+                        return;
+                    }
+                };
+                // Drain authentication/banner events until the session is ready.
+                while let Ok(event) = events.recv().await {
+                    if let wezterm_ssh::SessionEvent::Authenticated = event {
+                        break;
+                    }
+                }
+
+                let (pty, mut child) = match session
+                    .request_pty("xterm-256color", size, Some(&command_line), None)
+                    .await
+                {
+                    Ok(pair) => pair,
+                    Err(e) => {
+                        let _ = tx_clone.send(format!("ssh pty failed: {e}\n").into_bytes());
+                        return;
+                    }
+                };
+
+                let mut reader = match pty.try_clone_reader() {
+                    Ok(reader) => reader,
+                    Err(e) => {
+                        let _ = tx_clone.send(format!("ssh reader failed: {e}\n").into_bytes());
+                        return;
+                    }
+                };
+                let mut writer = pty.take_writer().ok();
+
+                // Pump remote output into the broadcast/ring path. When the
+                // remote child closes the PTY (EOF) the command has finished, so
+                // signal exit here rather than waiting for the command loop to
+                // end — otherwise a self-terminating remote command would leave
+                // `/status` reporting `running` and `/wait` hanging forever.
+                std::thread::spawn(move || {
+                    let mut buf = [0u8; 4096];
+                    loop {
+                        match reader.read(&mut buf) {
+                            Ok(0) => break,
+                            Ok(n) => {
+                                let chunk = buf[..n].to_vec();
+                                if let Some(rec) = recorder_reader.lock().unwrap().as_ref() {
+                                    rec.record_output(&chunk);
+                                }
+                                // Push and fan out under the ring lock so the
+                                // handoff to a late joiner has no gap (see
+                                // `LocalPtyBackend`).
+                                let mut ring = ring_clone.lock().unwrap();
+                                ring.push(&chunk);
+                                let _ = tx_clone.send(chunk);
+                            }
+                            Err(_) => break,
+                        }
+                    }
+                    exit_reader.finish(ExitStatus {
+                        code: None,
+                        exited_at: now_unix(),
+                    });
+                });
+
+                // Apply commands from the REST handlers.
+                while let Ok(cmd) = input_rx.recv() {
+                    match cmd {
+                        SshCommand::Write(data) => {
+                            if let Some(writer) = writer.as_mut() {
+                                let _ = writer.write_all(&data);
+                                let _ = writer.flush();
+                            }
+                        }
+                        SshCommand::Resize(cols, rows) => {
+                            let _ = pty.resize(PtySize {
+                                cols,
+                                rows,
+                                pixel_width: 0,
+                                pixel_height: 0,
+                            });
+                            if let Some(rec) = recorder_resize.lock().unwrap().as_ref() {
+                                rec.record_resize(cols, rows);
+                            }
+                        }
+                        SshCommand::Kill => {
+                            let _ = child.kill();
+                            break;
+                        }
+                    }
+                }
+            });
+
+            // The driver loop has ended (kill or dropped handle): the remote
+            // process is gone.
+            exit_clone.finish(ExitStatus {
+                code: None,
+                exited_at: now_unix(),
+            });
+        });
+
+        // Remote PIDs are not meaningful on the local host.
+        Ok((
+            Self {
+                input_tx,
+                output_tx,
+                ring,
+                exit,
+                recorder,
+                size: Mutex::new((spec.cols, spec.rows)),
+            },
+            0,
+        ))
+    }
+}
+
+impl PtyBackend for SshPtyBackend {
+    fn write(&self, data: &[u8]) -> Result<(), String> {
+        self.input_tx
+            .send(SshCommand::Write(data.to_vec()))
+            .map_err(|_| "ssh session closed".to_string())
+    }
+
+    fn resize(&self, cols: u16, rows: u16) -> Result<(), String> {
+        *self.size.lock().unwrap() = (cols, rows);
+        self.input_tx
+            .send(SshCommand::Resize(cols, rows))
+            .map_err(|_| "ssh session closed".to_string())
+    }
+
+    fn subscribe(&self, since: Option<u64>) -> (Vec<u8>, u64, broadcast::Receiver<Vec<u8>>) {
+        // Snapshot and subscribe under the same lock the reader holds for
+        // push+send, so the replay-to-live handoff has no gap.
+        let ring = self.ring.lock().unwrap();
+        let (backlog, next_seq) = ring.since(since);
+        let rx = self.output_tx.subscribe();
+        (backlog, next_seq, rx)
+    }
+
+    fn snapshot(&self, since: Option<u64>) -> (Vec<u8>, u64) {
+        self.ring.lock().unwrap().since(since)
+    }
+
+    fn start_recording(&self, path: std::path::PathBuf) -> Result<(), String> {
+        let (cols, rows) = *self.size.lock().unwrap();
+        let rec = Recorder::create(path, cols, rows)?;
+        *self.recorder.lock().unwrap() = Some(rec);
         Ok(())
     }
+
+    fn recording_path(&self) -> Option<std::path::PathBuf> {
+        self.recorder.lock().unwrap().as_ref().map(|r| r.path.clone())
+    }
+
+    fn kill(&self) -> Result<(), String> {
+        let _ = self.input_tx.send(SshCommand::Kill);
+        Ok(())
+    }
+
+    fn exit(&self) -> Arc<ExitTracker> {
+        self.exit.clone()
+    }
+}
+
+/// Replays a stored asciinema cast as a session: a background thread reads the
+/// cast, sleeps to honour the original timing, and publishes each output event
+/// into the same broadcast/ring path that live sessions use.
+struct PlaybackBackend {
+    output_tx: broadcast::Sender<Vec<u8>>,
+    ring: Arc<Mutex<RingBuffer>>,
+    exit: Arc<ExitTracker>,
+}
+
+impl PlaybackBackend {
+    fn open(path: std::path::PathBuf) -> Result<Self, String> {
+        use std::io::BufRead;
+
+        let file = std::fs::File::open(&path).map_err(|e| format!("recording open failed: {e}"))?;
+        let (output_tx, _) = broadcast::channel(BROADCAST_CAPACITY);
+        let ring = Arc::new(Mutex::new(RingBuffer::new(
+            RING_CAPACITY_BYTES,
+            RING_CAPACITY_LINES,
+        )));
+        let exit = ExitTracker::new();
+
+        let tx_clone = output_tx.clone();
+        let ring_clone = ring.clone();
+        let exit_clone = exit.clone();
+        std::thread::spawn(move || {
+            let reader = std::io::BufReader::new(file);
+            let mut lines = reader.lines();
+            // Skip the header line.
+            let _ = lines.next();
+
+            let start = std::time::Instant::now();
+            for line in lines.flatten() {
+                let event: serde_json::Value = match serde_json::from_str(&line) {
+                    Ok(value) => value,
+                    Err(_) => continue,
+                };
+                let elapsed = event.get(0).and_then(|v| v.as_f64()).unwrap_or(0.0);
+                let code = event.get(1).and_then(|v| v.as_str()).unwrap_or("");
+                if code != "o" {
+                    continue;
+                }
+                let data = event.get(2).and_then(|v| v.as_str()).unwrap_or("");
+
+                // Sleep until this event's original offset.
+                let target = std::time::Duration::from_secs_f64(elapsed);
+                let now = start.elapsed();
+                if target > now {
+                    std::thread::sleep(target - now);
+                }
+
+                let chunk = data.as_bytes().to_vec();
+                // Push and fan out under the ring lock so the handoff to a late
+                // joiner has no gap (see `LocalPtyBackend`).
+                let mut ring = ring_clone.lock().unwrap();
+                ring.push(&chunk);
+                let _ = tx_clone.send(chunk);
+            }
+
+            exit_clone.finish(ExitStatus {
+                code: Some(0),
+                exited_at: now_unix(),
+            });
+        });
+
+        Ok(Self {
+            output_tx,
+            ring,
+            exit,
+        })
+    }
+}
+
+impl PtyBackend for PlaybackBackend {
+    fn write(&self, _data: &[u8]) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn resize(&self, _cols: u16, _rows: u16) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn subscribe(&self, since: Option<u64>) -> (Vec<u8>, u64, broadcast::Receiver<Vec<u8>>) {
+        // Snapshot and subscribe under the same lock the replay thread holds for
+        // push+send, so the replay-to-live handoff has no gap.
+        let ring = self.ring.lock().unwrap();
+        let (backlog, next_seq) = ring.since(since);
+        let rx = self.output_tx.subscribe();
+        (backlog, next_seq, rx)
+    }
+
+    fn snapshot(&self, since: Option<u64>) -> (Vec<u8>, u64) {
+        self.ring.lock().unwrap().since(since)
+    }
+
+    fn kill(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn exit(&self) -> Arc<ExitTracker> {
+        self.exit.clone()
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -148,6 +1116,14 @@ struct CreateRequest {
     cwd: Option<String>,
     cols: Option<u16>,
     rows: Option<u16>,
+    /// Optional remote host; when absent the terminal runs locally.
+    ssh: Option<SshConfig>,
+    /// Retained scrollback caps; default to the server-wide limits.
+    scrollback_bytes: Option<usize>,
+    scrollback_lines: Option<usize>,
+    /// Record this session to an asciinema cast file from the start.
+    #[serde(default)]
+    record: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -167,41 +1143,225 @@ struct ResizeRequest {
     rows: u16,
 }
 
-async fn create_session(State(state): State<AppState>, Json(req): Json<CreateRequest>) -> Result<Json<CreateResponse>, String> {
-    let (id, pid) = state.manager.create_session(req.command, req.cwd, req.cols, req.rows)?;
+#[derive(Debug, Serialize)]
+struct StatusResponse {
+    running: bool,
+    exit: Option<ExitStatus>,
+}
+
+/// Resume point for `/read` and `/stream`, as a byte sequence number. `since`
+/// and its `offset` alias are interchangeable; `since` wins if both are set.
+#[derive(Debug, Deserialize)]
+struct ReplayParams {
+    since: Option<u64>,
+    offset: Option<u64>,
+}
+
+impl ReplayParams {
+    fn seq(&self) -> Option<u64> {
+        self.since.or(self.offset)
+    }
+}
+
+async fn create_session(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Json(req): Json<CreateRequest>,
+) -> Result<Json<CreateResponse>, String> {
+    let (id, pid) = state.manager.create_session(
+        req.command,
+        req.cwd,
+        req.cols,
+        req.rows,
+        req.ssh,
+        req.scrollback_bytes,
+        req.scrollback_lines,
+        req.record,
+        &identity,
+    )?;
     Ok(Json(CreateResponse { id, pid }))
 }
 
-async fn send_input(State(state): State<AppState>, Path(id): Path<String>, Json(req): Json<InputRequest>) -> Result<(), String> {
-    state.manager.write(&id, req.data.as_bytes())?;
+async fn list_sessions(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+) -> Json<Vec<SessionInfo>> {
+    Json(state.manager.list(&identity))
+}
+
+async fn send_input(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Path(id): Path<String>,
+    Json(req): Json<InputRequest>,
+) -> Result<(), String> {
+    state.manager.write(&id, req.data.as_bytes(), &identity)?;
     Ok(())
 }
 
-async fn resize(State(state): State<AppState>, Path(id): Path<String>, Json(req): Json<ResizeRequest>) -> Result<(), String> {
-    state.manager.resize(&id, req.cols, req.rows)?;
+async fn resize(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Path(id): Path<String>,
+    Json(req): Json<ResizeRequest>,
+) -> Result<(), String> {
+    state.manager.resize(&id, req.cols, req.rows, &identity)?;
     Ok(())
 }
 
-async fn read_output(State(state): State<AppState>, Path(id): Path<String>) -> Result<Vec<u8>, String> {
-    state.manager.read(&id)
+async fn read_output(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Path(id): Path<String>,
+    Query(params): Query<ReplayParams>,
+) -> Result<(HeaderMap, Vec<u8>), String> {
+    let (data, next_seq) = state.manager.read(&id, params.seq(), &identity)?;
+    // Expose the tail sequence so a polling client can resume from it.
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "X-Next-Seq",
+        HeaderValue::from_str(&next_seq.to_string()).unwrap(),
+    );
+    Ok((headers, data))
 }
 
-async fn kill_session(State(state): State<AppState>, Path(id): Path<String>) -> Result<(), String> {
-    state.manager.kill(&id)?;
+async fn kill_session(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Path(id): Path<String>,
+) -> Result<(), String> {
+    state.manager.kill(&id, &identity)?;
     Ok(())
 }
 
+async fn session_status(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Path(id): Path<String>,
+) -> Result<Json<StatusResponse>, String> {
+    Ok(Json(state.manager.status(&id, &identity)?))
+}
+
+async fn wait_session(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Path(id): Path<String>,
+) -> Result<Json<ExitStatus>, String> {
+    let tracker = state.manager.exit_tracker(&id, &identity)?;
+    Ok(Json(tracker.wait().await))
+}
+
+async fn record_session(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Path(id): Path<String>,
+) -> Result<(), String> {
+    state.manager.start_recording(&id, &identity)
+}
+
+async fn download_recording(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Path(id): Path<String>,
+) -> Result<Vec<u8>, String> {
+    state.manager.recording(&id, &identity)
+}
+
+async fn play_recording(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Path(name): Path<String>,
+) -> Result<Json<CreateResponse>, String> {
+    let (id, pid) = state.manager.play_recording(&name, &identity)?;
+    Ok(Json(CreateResponse { id, pid }))
+}
+
+async fn stream_output(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Path(id): Path<String>,
+    Query(params): Query<ReplayParams>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, String> {
+    let (backlog, _next_seq, rx) = state.manager.subscribe(&id, params.seq(), &identity)?;
+    Ok(ws.on_upgrade(move |socket| stream_session(socket, backlog, rx)))
+}
+
+async fn stream_session(
+    mut socket: WebSocket,
+    backlog: Vec<u8>,
+    mut rx: broadcast::Receiver<Vec<u8>>,
+) {
+    // Replay retained scrollback before switching to the live tail.
+    if !backlog.is_empty() && socket.send(Message::Binary(backlog)).await.is_err() {
+        return;
+    }
+
+    loop {
+        match rx.recv().await {
+            Ok(chunk) => {
+                if socket.send(Message::Binary(chunk)).await.is_err() {
+                    break;
+                }
+            }
+            // Lagged: the subscriber fell behind; skip ahead rather than abort.
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Authenticate a request by its `Authorization: Bearer <token>` header and
+/// insert the resolved [`Identity`] into the request extensions for handlers to
+/// extract. Requests without a valid token are rejected with `401`.
+async fn require_auth(
+    State(state): State<AppState>,
+    mut req: Request<axum::body::Body>,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let token = req
+        .headers()
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim);
+
+    let identity = token
+        .and_then(|token| state.auth.identify(token))
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    req.extensions_mut().insert(identity);
+    Ok(next.run(req).await)
+}
+
 #[tokio::main]
 async fn main() {
     let manager = Arc::new(TerminalManager::new());
-    let state = AppState { manager };
+    let auth = Auth::from_env();
+    if auth.admin.is_none() && auth.tokens.is_empty() {
+        eprintln!(
+            "warning: no VT_ADMIN_TOKEN or VT_TOKENS configured; every request \
+             will be rejected with 401 until at least one token is set"
+        );
+    }
+    let state = AppState {
+        manager,
+        auth: Arc::new(auth),
+    };
 
     let app = Router::new()
-        .route("/sessions", post(create_session))
+        .route("/sessions", post(create_session).get(list_sessions))
         .route("/sessions/:id/input", post(send_input))
         .route("/sessions/:id/resize", post(resize))
         .route("/sessions/:id/read", get(read_output))
+        .route("/sessions/:id/stream", get(stream_output))
+        .route("/sessions/:id/status", get(session_status))
+        .route("/sessions/:id/wait", get(wait_session))
+        .route("/sessions/:id/record", post(record_session))
+        .route("/sessions/:id/recording", get(download_recording))
+        .route("/recordings/:name/play", post(play_recording))
         .route("/sessions/:id", delete(kill_session))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_auth))
         .with_state(state);
 
     let listener = tokio::net::TcpListener::bind("127.0.0.1:4030")
@@ -210,3 +1370,75 @@ async fn main() {
     axum::serve(listener, app).await.unwrap();
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ring_since_returns_only_bytes_after_cursor() {
+        let mut ring = RingBuffer::new(1024, 1024);
+        ring.push(b"hello");
+        ring.push(b" world");
+
+        // No cursor (or seq 0) replays everything; end is one past the last byte.
+        assert_eq!(ring.since(None), (b"hello world".to_vec(), 11));
+        assert_eq!(ring.since(Some(0)), (b"hello world".to_vec(), 11));
+
+        // A mid-stream cursor returns exactly the tail after it.
+        assert_eq!(ring.since(Some(5)), (b" world".to_vec(), 11));
+
+        // A cursor at (or past) the tail returns nothing but the current end.
+        assert_eq!(ring.since(Some(11)), (Vec::new(), 11));
+        assert_eq!(ring.since(Some(99)), (Vec::new(), 11));
+    }
+
+    #[test]
+    fn ring_trims_to_byte_cap_and_advances_start_seq() {
+        let mut ring = RingBuffer::new(4, 1024);
+        ring.push(b"abcdef");
+
+        // Only the last 4 bytes are retained; start_seq tracks what was dropped.
+        assert_eq!(ring.start_seq, 2);
+        assert_eq!(ring.since(None), (b"cdef".to_vec(), 6));
+        // A cursor pointing into dropped bytes is clamped to the retained head.
+        assert_eq!(ring.since(Some(0)), (b"cdef".to_vec(), 6));
+    }
+
+    #[test]
+    fn ring_trims_to_line_cap() {
+        let mut ring = RingBuffer::new(1024, 2);
+        ring.push(b"one\ntwo\nthree\n");
+
+        // Oldest whole lines drop until at most `line_cap` newlines remain.
+        assert_eq!(ring.since(None).0, b"two\nthree\n".to_vec());
+    }
+
+    #[test]
+    fn auth_distinguishes_admin_ordinary_and_unknown_tokens() {
+        let auth = Auth {
+            admin: Some("root".to_string()),
+            tokens: ["alice", "bob"].iter().map(|t| t.to_string()).collect(),
+        };
+
+        let admin = auth.identify("root").expect("admin token");
+        assert!(admin.admin);
+        assert_eq!(admin.token, "root");
+
+        let ordinary = auth.identify("alice").expect("ordinary token");
+        assert!(!ordinary.admin);
+        assert_eq!(ordinary.token, "alice");
+
+        assert!(auth.identify("mallory").is_none());
+    }
+
+    #[test]
+    fn auth_rejects_everything_when_unconfigured() {
+        let auth = Auth {
+            admin: None,
+            tokens: HashSet::new(),
+        };
+        assert!(auth.identify("").is_none());
+        assert!(auth.identify("anything").is_none());
+    }
+}